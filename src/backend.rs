@@ -0,0 +1,423 @@
+//! Clipboard backends.
+//!
+//! The historical implementation linked directly against the `clipboard`
+//! crate, which in turn pulls in `libxcb` on Linux and fails to build (or
+//! even just to run) on headless machines and containers. This module
+//! introduces a small abstraction, [`ClipboardBackend`], so callers don't
+//! care whether reads/writes go through an external command-line tool or
+//! an in-process X11/Wayland binding.
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Which clipboard buffer an operation targets.
+///
+/// On X11 and Wayland there are two independent buffers: the "system"
+/// clipboard (explicit copy/paste) and the "primary selection" (the
+/// middle-click buffer familiar from most Linux editors and terminals).
+/// Platforms without a primary selection treat [`ClipboardType::Selection`]
+/// as an alias for [`ClipboardType::Clipboard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClipboardType {
+    #[default]
+    Clipboard,
+    Selection,
+}
+
+impl fmt::Display for ClipboardType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardType::Clipboard => write!(f, "clipboard"),
+            ClipboardType::Selection => write!(f, "selection"),
+        }
+    }
+}
+
+/// A source or destination for clipboard contents.
+///
+/// Implementors only need to know how to read and write plain UTF-8 text;
+/// selection between backends happens once, at startup, via
+/// [`detect_backend`].
+pub trait ClipboardBackend {
+    /// A short, human-readable name for diagnostics (e.g. `"wl-copy/wl-paste"`).
+    fn name(&self) -> &str;
+
+    /// Read the current contents of `target`.
+    fn get_contents(&self, target: ClipboardType) -> Result<String, Box<dyn Error>>;
+
+    /// Replace the contents of `target`.
+    fn set_contents(&self, target: ClipboardType, value: String) -> Result<(), Box<dyn Error>>;
+
+    /// Whether this backend has a real, independent primary selection.
+    /// Used for diagnostics and to decide whether to offer a choice of
+    /// buffer; `get_contents`/`set_contents` already fall back to the system
+    /// clipboard when this is `false`.
+    fn supports_selection(&self) -> bool {
+        false
+    }
+
+    /// The resolved path of the executable this backend shells out to, if
+    /// any. `None` for in-process backends.
+    fn executable_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Sentinel string used by [`self_test`] to verify a backend can round-trip
+/// a write followed by a read.
+const SELF_TEST_SENTINEL: &str = "rusty_clip_commander::self_test::9f2b6c";
+
+/// Write [`SELF_TEST_SENTINEL`] to the clipboard and read it back, to give
+/// users something more actionable than an opaque `Box<dyn Error>` bubbling
+/// out of `save`/`load`.
+///
+/// This necessarily overwrites the clipboard's current contents, so
+/// whatever was there beforehand is saved first and restored afterward on a
+/// best-effort basis (if the initial read fails, there's nothing to
+/// restore, and the test still runs).
+pub fn self_test(backend: &dyn ClipboardBackend) -> Result<(), Box<dyn Error>> {
+    let original = backend.get_contents(ClipboardType::Clipboard).ok();
+    let result = run_round_trip(backend);
+    if let Some(original) = original {
+        let _ = backend.set_contents(ClipboardType::Clipboard, original);
+    }
+    result
+}
+
+fn run_round_trip(backend: &dyn ClipboardBackend) -> Result<(), Box<dyn Error>> {
+    backend.set_contents(ClipboardType::Clipboard, SELF_TEST_SENTINEL.to_string())?;
+    let read_back = backend.get_contents(ClipboardType::Clipboard)?;
+    if read_back.trim_end_matches('\n') == SELF_TEST_SENTINEL {
+        Ok(())
+    } else {
+        Err(format!(
+            "round-trip mismatch: wrote {:?}, read back {:?}",
+            SELF_TEST_SENTINEL, read_back
+        )
+        .into())
+    }
+}
+
+/// An external program and the arguments used to invoke it.
+struct Cmd {
+    program: &'static str,
+    args: Vec<&'static str>,
+}
+
+impl Cmd {
+    fn new(program: &'static str, args: Vec<&'static str>) -> Self {
+        Self { program, args }
+    }
+}
+
+/// A copy/paste command pair for one clipboard buffer.
+struct CommandPair {
+    copy: Cmd,
+    paste: Cmd,
+}
+
+/// A backend that shells out to an external command-line tool.
+///
+/// `clipboard` is always present; `selection` is `None` on backends/platforms
+/// with no independent primary selection, in which case operations on
+/// [`ClipboardType::Selection`] transparently fall back to `clipboard`.
+pub struct CommandBackend {
+    label: &'static str,
+    clipboard: CommandPair,
+    selection: Option<CommandPair>,
+}
+
+impl CommandBackend {
+    fn new(label: &'static str, clipboard: CommandPair, selection: Option<CommandPair>) -> Self {
+        Self {
+            label,
+            clipboard,
+            selection,
+        }
+    }
+
+    fn pair(&self, target: ClipboardType) -> &CommandPair {
+        match target {
+            ClipboardType::Clipboard => &self.clipboard,
+            ClipboardType::Selection => self.selection.as_ref().unwrap_or(&self.clipboard),
+        }
+    }
+}
+
+impl ClipboardBackend for CommandBackend {
+    fn name(&self) -> &str {
+        self.label
+    }
+
+    fn supports_selection(&self) -> bool {
+        self.selection.is_some()
+    }
+
+    fn executable_path(&self) -> Option<PathBuf> {
+        which_path(self.clipboard.paste.program)
+    }
+
+    fn get_contents(&self, target: ClipboardType) -> Result<String, Box<dyn Error>> {
+        let Cmd { program, args } = &self.pair(target).paste;
+        let output = Command::new(program).args(args).output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "`{} {}` exited with {}",
+                program,
+                args.join(" "),
+                output.status
+            )
+            .into());
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    fn set_contents(&self, target: ClipboardType, value: String) -> Result<(), Box<dyn Error>> {
+        let Cmd { program, args } = &self.pair(target).copy;
+        let mut child = Command::new(program)
+            .args(args.clone())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .as_mut()
+            .ok_or("failed to open stdin for clipboard command")?
+            .write_all(value.as_bytes())?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("`{} {}` exited with {}", program, args.join(" "), status).into());
+        }
+        Ok(())
+    }
+}
+
+/// The in-process backend backed by the `clipboard` crate.
+///
+/// Only compiled in when the `native-clipboard` feature is enabled, since
+/// it is what pulls in `libxcb` on Linux. The `clipboard` crate has no
+/// notion of the primary selection, so `Selection` always maps to the
+/// system clipboard here.
+#[cfg(feature = "native-clipboard")]
+pub struct NativeBackend;
+
+#[cfg(feature = "native-clipboard")]
+impl ClipboardBackend for NativeBackend {
+    fn name(&self) -> &str {
+        "clipboard (native)"
+    }
+
+    fn get_contents(&self, _target: ClipboardType) -> Result<String, Box<dyn Error>> {
+        use clipboard::{ClipboardContext, ClipboardProvider};
+        let mut ctx: ClipboardContext = ClipboardProvider::new()?;
+        Ok(ctx.get_contents()?)
+    }
+
+    fn set_contents(&self, _target: ClipboardType, value: String) -> Result<(), Box<dyn Error>> {
+        use clipboard::{ClipboardContext, ClipboardProvider};
+        let mut ctx: ClipboardContext = ClipboardProvider::new()?;
+        ctx.set_contents(value)?;
+        Ok(())
+    }
+}
+
+/// Resolves `program` to a full path on `PATH`, like the POSIX `which`
+/// command but without shelling out to it. Returns the first regular file of
+/// that name; unlike `which`, the executable bit is not checked.
+fn which_path(program: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Returns `true` if `program` resolves to a file on `PATH` (see [`which_path`]).
+fn which(program: &str) -> bool {
+    which_path(program).is_some()
+}
+
+/// Probe the system for the best available clipboard backend.
+///
+/// Detection order:
+/// 1. On Linux, `wl-copy`/`wl-paste` if `WAYLAND_DISPLAY` is set.
+/// 2. On Linux, `xclip -selection clipboard`, then `xsel -b`.
+/// 3. On macOS, `pbcopy`/`pbpaste`.
+/// 4. On Windows, `clip.exe` plus PowerShell `Get-Clipboard`.
+/// 5. The in-process `clipboard` crate, if the `native-clipboard` feature
+///    is enabled.
+///
+/// Returns `None` if nothing usable was found.
+pub fn detect_backend() -> Option<Box<dyn ClipboardBackend>> {
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() && which("wl-copy") && which("wl-paste") {
+            return Some(Box::new(CommandBackend::new(
+                "wl-copy/wl-paste",
+                CommandPair {
+                    copy: Cmd::new("wl-copy", vec![]),
+                    paste: Cmd::new("wl-paste", vec!["--no-newline"]),
+                },
+                Some(CommandPair {
+                    copy: Cmd::new("wl-copy", vec!["--primary"]),
+                    paste: Cmd::new("wl-paste", vec!["--primary", "--no-newline"]),
+                }),
+            )));
+        }
+        if which("xclip") {
+            return Some(Box::new(CommandBackend::new(
+                "xclip",
+                CommandPair {
+                    copy: Cmd::new("xclip", vec!["-selection", "clipboard"]),
+                    paste: Cmd::new("xclip", vec!["-selection", "clipboard", "-o"]),
+                },
+                Some(CommandPair {
+                    copy: Cmd::new("xclip", vec!["-selection", "primary"]),
+                    paste: Cmd::new("xclip", vec!["-selection", "primary", "-o"]),
+                }),
+            )));
+        }
+        if which("xsel") {
+            return Some(Box::new(CommandBackend::new(
+                "xsel",
+                CommandPair {
+                    copy: Cmd::new("xsel", vec!["-b", "-i"]),
+                    paste: Cmd::new("xsel", vec!["-b", "-o"]),
+                },
+                Some(CommandPair {
+                    copy: Cmd::new("xsel", vec!["-p", "-i"]),
+                    paste: Cmd::new("xsel", vec!["-p", "-o"]),
+                }),
+            )));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if which("pbcopy") && which("pbpaste") {
+            return Some(Box::new(CommandBackend::new(
+                "pbcopy/pbpaste",
+                CommandPair {
+                    copy: Cmd::new("pbcopy", vec![]),
+                    paste: Cmd::new("pbpaste", vec![]),
+                },
+                None,
+            )));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if which("clip.exe") && which("powershell.exe") {
+            return Some(Box::new(CommandBackend::new(
+                "clip.exe/Get-Clipboard",
+                CommandPair {
+                    copy: Cmd::new("clip.exe", vec![]),
+                    paste: Cmd::new("powershell.exe", vec!["-command", "Get-Clipboard"]),
+                },
+                None,
+            )));
+        }
+    }
+
+    #[cfg(feature = "native-clipboard")]
+    {
+        return Some(Box::new(NativeBackend));
+    }
+
+    #[cfg(not(feature = "native-clipboard"))]
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    /// An in-memory backend for exercising `self_test` without touching a
+    /// real clipboard or spawning external commands.
+    struct MockBackend {
+        clipboard: RefCell<String>,
+        corrupt_reads: Cell<bool>,
+    }
+
+    impl MockBackend {
+        fn with_contents(contents: &str) -> Self {
+            Self {
+                clipboard: RefCell::new(contents.to_string()),
+                corrupt_reads: Cell::new(false),
+            }
+        }
+    }
+
+    impl ClipboardBackend for MockBackend {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn get_contents(&self, _target: ClipboardType) -> Result<String, Box<dyn Error>> {
+            if self.corrupt_reads.get() {
+                Ok("not the sentinel".to_string())
+            } else {
+                Ok(self.clipboard.borrow().clone())
+            }
+        }
+
+        fn set_contents(&self, _target: ClipboardType, value: String) -> Result<(), Box<dyn Error>> {
+            *self.clipboard.borrow_mut() = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn self_test_restores_the_clipboards_prior_contents() {
+        let backend = MockBackend::with_contents("what the user had copied");
+        assert!(self_test(&backend).is_ok());
+        assert_eq!(*backend.clipboard.borrow(), "what the user had copied");
+    }
+
+    #[test]
+    fn self_test_reports_a_round_trip_mismatch_as_an_error() {
+        let backend = MockBackend::with_contents("original");
+        backend.corrupt_reads.set(true);
+        let err = self_test(&backend).unwrap_err();
+        assert!(err.to_string().contains("round-trip mismatch"));
+    }
+
+    #[test]
+    fn pair_falls_back_to_the_clipboard_pair_when_no_selection_pair_is_configured() {
+        let backend = CommandBackend::new(
+            "test",
+            CommandPair {
+                copy: Cmd::new("clipboard-copy", vec![]),
+                paste: Cmd::new("clipboard-paste", vec![]),
+            },
+            None,
+        );
+        assert_eq!(
+            backend.pair(ClipboardType::Selection).paste.program,
+            "clipboard-paste"
+        );
+    }
+
+    #[test]
+    fn pair_uses_the_dedicated_selection_pair_when_configured() {
+        let backend = CommandBackend::new(
+            "test",
+            CommandPair {
+                copy: Cmd::new("clipboard-copy", vec![]),
+                paste: Cmd::new("clipboard-paste", vec![]),
+            },
+            Some(CommandPair {
+                copy: Cmd::new("selection-copy", vec![]),
+                paste: Cmd::new("selection-paste", vec![]),
+            }),
+        );
+        assert_eq!(
+            backend.pair(ClipboardType::Selection).paste.program,
+            "selection-paste"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Minimal command-line argument parsing for non-interactive use.
+//!
+//! Running the binary with no arguments falls back to the interactive
+//! `dialoguer` menu; any other invocation is parsed here so the tool can be
+//! driven from shell pipelines, e.g.
+//! `echo "secret" | rusty_clip_commander save notes api-key`.
+
+use std::str::FromStr;
+
+/// Output format for `list`/`search`, reusing the same serializers as
+/// `export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("unsupported format: {other} (expected json or csv)")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Save { history: String, key: String },
+    Load { history: String, key: String },
+    List { format: OutputFormat },
+    Search { term: String, format: OutputFormat },
+    Provider,
+}
+
+fn parse_format_flag(rest: &[String]) -> Result<OutputFormat, String> {
+    match rest {
+        [] => Ok(OutputFormat::Json),
+        [flag, value] if flag == "--format" => value.parse(),
+        _ => Err("usage: --format <json|csv>".to_string()),
+    }
+}
+
+/// Parse `argv[1..]`. Returns `Ok(None)` when `args` is empty, signalling
+/// the caller should fall back to the interactive menu.
+pub fn parse(args: &[String]) -> Result<Option<Command>, String> {
+    let Some((subcommand, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+
+    match subcommand.as_str() {
+        "save" => match rest {
+            [history, key] => Ok(Some(Command::Save {
+                history: history.clone(),
+                key: key.clone(),
+            })),
+            _ => Err("usage: save <history> <key>".to_string()),
+        },
+        "load" => match rest {
+            [history, key] => Ok(Some(Command::Load {
+                history: history.clone(),
+                key: key.clone(),
+            })),
+            _ => Err("usage: load <history> <key>".to_string()),
+        },
+        "list" => Ok(Some(Command::List {
+            format: parse_format_flag(rest)?,
+        })),
+        "search" => match rest.split_first() {
+            Some((term, format_args)) => Ok(Some(Command::Search {
+                term: term.clone(),
+                format: parse_format_flag(format_args)?,
+            })),
+            None => Err("usage: search <term> [--format json|csv]".to_string()),
+        },
+        "provider" => match rest {
+            [] => Ok(Some(Command::Provider)),
+            _ => Err("usage: provider".to_string()),
+        },
+        other => Err(format!("unknown command: {other}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_args_fall_back_to_the_interactive_menu() {
+        assert!(matches!(parse(&args(&[])), Ok(None)));
+    }
+
+    #[test]
+    fn save_requires_history_and_key() {
+        assert!(matches!(
+            parse(&args(&["save", "notes", "api-key"])),
+            Ok(Some(Command::Save { .. }))
+        ));
+        assert!(parse(&args(&["save", "notes"])).is_err());
+        assert!(parse(&args(&["save", "notes", "api-key", "extra"])).is_err());
+    }
+
+    #[test]
+    fn load_requires_history_and_key() {
+        assert!(matches!(
+            parse(&args(&["load", "notes", "api-key"])),
+            Ok(Some(Command::Load { .. }))
+        ));
+        assert!(parse(&args(&["load"])).is_err());
+    }
+
+    #[test]
+    fn list_defaults_to_json_and_accepts_an_explicit_format() {
+        assert!(matches!(
+            parse(&args(&["list"])),
+            Ok(Some(Command::List {
+                format: OutputFormat::Json
+            }))
+        ));
+        assert!(matches!(
+            parse(&args(&["list", "--format", "csv"])),
+            Ok(Some(Command::List {
+                format: OutputFormat::Csv
+            }))
+        ));
+        assert!(parse(&args(&["list", "--format", "xml"])).is_err());
+        assert!(parse(&args(&["list", "bogus"])).is_err());
+    }
+
+    #[test]
+    fn search_requires_a_term_and_accepts_an_explicit_format() {
+        match parse(&args(&["search", "todo"])) {
+            Ok(Some(Command::Search { term, format })) => {
+                assert_eq!(term, "todo");
+                assert_eq!(format, OutputFormat::Json);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(
+            parse(&args(&["search", "todo", "--format", "csv"])),
+            Ok(Some(Command::Search {
+                format: OutputFormat::Csv,
+                ..
+            }))
+        ));
+        assert!(parse(&args(&["search"])).is_err());
+    }
+
+    #[test]
+    fn provider_takes_no_arguments() {
+        assert!(matches!(
+            parse(&args(&["provider"])),
+            Ok(Some(Command::Provider))
+        ));
+        assert!(parse(&args(&["provider", "extra"])).is_err());
+    }
+
+    #[test]
+    fn unknown_subcommand_is_an_error() {
+        assert!(parse(&args(&["frobnicate"])).is_err());
+    }
+}
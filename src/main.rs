@@ -1,19 +1,168 @@
-use clipboard::{ClipboardContext, ClipboardProvider};
+mod backend;
+mod cli;
+
+use backend::{ClipboardBackend, ClipboardType};
+use cli::{Command, OutputFormat};
 use dialoguer::{theme::ColorfulTheme, Input, Select};
 use prettytable::{format, row, Cell, Row, Table};
+use seahash::SeaHasher;
 use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs::{self, File};
-use std::io::{BufWriter, Write};
+use std::hash::Hasher;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
 use std::path::Path;
 use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A seahash digest of `text`'s UTF-8 bytes, used to detect duplicate
+/// entries without comparing the full text.
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = SeaHasher::default();
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// On-disk shapes this crate has stored entries as over time, oldest first.
+/// Deserializing through this enum lets `clipboard.json` files written by
+/// earlier versions keep loading.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawEntry {
+    Legacy(String),
+    WithoutHash {
+        value: String,
+        #[serde(default)]
+        selection: ClipboardType,
+    },
+    Current {
+        text: String,
+        hash: u64,
+        #[serde(default)]
+        selection: ClipboardType,
+        #[serde(default)]
+        metadata: Option<String>,
+        #[serde(default)]
+        created_at: u64,
+    },
+}
+
+impl From<RawEntry> for Entry {
+    fn from(raw: RawEntry) -> Self {
+        match raw {
+            RawEntry::Legacy(text) => {
+                let hash = hash_text(&text);
+                Entry {
+                    text,
+                    hash,
+                    selection: ClipboardType::default(),
+                    metadata: None,
+                    created_at: now(),
+                }
+            }
+            RawEntry::WithoutHash { value, selection } => {
+                let hash = hash_text(&value);
+                Entry {
+                    text: value,
+                    hash,
+                    selection,
+                    metadata: None,
+                    created_at: now(),
+                }
+            }
+            RawEntry::Current {
+                text,
+                hash,
+                selection,
+                metadata,
+                created_at,
+            } => Entry {
+                text,
+                hash,
+                selection,
+                metadata,
+                created_at,
+            },
+        }
+    }
+}
+
+/// A single stored clipboard entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawEntry")]
+struct Entry {
+    text: String,
+    hash: u64,
+    #[serde(default)]
+    selection: ClipboardType,
+    #[serde(default)]
+    metadata: Option<String>,
+    #[serde(default)]
+    created_at: u64,
+}
+
+impl Entry {
+    fn new(text: String, selection: ClipboardType) -> Self {
+        let hash = hash_text(&text);
+        Entry {
+            text,
+            hash,
+            selection,
+            metadata: None,
+            created_at: now(),
+        }
+    }
+}
+
+/// Whether another entry in the same history shares `entry`'s content hash.
+fn is_duplicate(history: &HashMap<String, Entry>, entry: &Entry) -> bool {
+    history
+        .values()
+        .any(|other| !std::ptr::eq(other, entry) && other.hash == entry.hash)
+}
+
+type Data = HashMap<String, HashMap<String, Entry>>;
+
+fn write_json<W: Write>(data: &Data, writer: W) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer_pretty(writer, data)?;
+    Ok(())
+}
+
+fn write_csv<W: Write>(data: &Data, writer: W) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for (history_name, map) in data {
+        for (key, entry) in map {
+            writer.serialize([
+                history_name,
+                key,
+                &entry.text,
+                &entry.selection.to_string(),
+                entry.metadata.as_deref().unwrap_or(""),
+            ])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
 
-#[derive(Debug, Deserialize, Serialize)]
 struct Clipboard {
-    data: HashMap<String, HashMap<String, String>>,
+    data: Data,
     filepath: String,
     current: String,
+    /// Detected lazily, on first use: commands like `list`/`search` never
+    /// touch the clipboard, so they shouldn't pay for probing `wl-copy`,
+    /// `xclip`, etc., or print a "no backend" warning that isn't relevant
+    /// to what they're doing.
+    backend: OnceCell<Option<Box<dyn ClipboardBackend>>>,
 }
 
 impl Clipboard {
@@ -22,9 +171,36 @@ impl Clipboard {
             data: HashMap::new(),
             filepath: filepath.to_string(),
             current: "default".to_string(),
+            backend: OnceCell::new(),
         }
     }
 
+    /// The detected backend, probing for one on first access.
+    fn backend(&self) -> &Option<Box<dyn ClipboardBackend>> {
+        self.backend.get_or_init(|| {
+            let backend = backend::detect_backend();
+            match &backend {
+                Some(backend) => eprintln!("Using clipboard backend: {}", backend.name()),
+                None => eprintln!(
+                    "No clipboard backend detected (tried wl-copy/wl-paste, xclip, xsel, pbcopy/pbpaste, clip.exe; \
+                     build with --features native-clipboard for the bundled fallback). \
+                     Actions that touch the clipboard will fail until one is available; run `provider` for details."
+                ),
+            }
+            backend
+        })
+    }
+
+    /// The active backend, or an error describing what's missing. Only
+    /// called by actions that actually touch the clipboard, so `list`,
+    /// `search`, and the "no backend" branch of `provider` work fine
+    /// without one.
+    fn require_backend(&self) -> Result<&dyn ClipboardBackend, Box<dyn Error>> {
+        self.backend().as_deref().ok_or_else(|| {
+            "no clipboard backend available (install xclip, xsel, wl-clipboard, or build with --features native-clipboard)".into()
+        })
+    }
+
     fn load_data(&mut self) -> Result<(), Box<dyn Error>> {
         let file_content = fs::read_to_string(&self.filepath)?;
         self.data = serde_json::from_str(&file_content)?;
@@ -42,17 +218,30 @@ impl Clipboard {
         let history_name = Input::<String>::with_theme(&ColorfulTheme::default())
             .with_prompt("Enter clipboard history name:")
             .interact()?;
-        let mut clipboard_ctx: ClipboardContext = ClipboardProvider::new()?;
-        let value = clipboard_ctx.get_contents()?.to_owned();
-        self.data
-            .entry(history_name.clone())
-            .or_insert(HashMap::new())
-            .insert(
-                Input::<String>::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Enter key:")
-                    .interact()?,
-                value,
+        let selection = self.prompt_selection("Copy from:", ClipboardType::Clipboard)?;
+        let entry = Entry::new(self.require_backend()?.get_contents(selection)?, selection);
+        let history = self.data.entry(history_name.clone()).or_default();
+
+        if let Some(existing_key) = history.iter().find_map(|(k, v)| {
+            if v.hash == entry.hash {
+                Some(k.clone())
+            } else {
+                None
+            }
+        }) {
+            println!(
+                "Skipped: this text is already stored in \"{}\" under key \"{}\".",
+                history_name, existing_key
             );
+            return Ok(());
+        }
+
+        history.insert(
+            Input::<String>::with_theme(&ColorfulTheme::default())
+                .with_prompt("Enter key:")
+                .interact()?,
+            entry,
+        );
         self.save_data()?;
         println!("Data saved to clipboard history: {}", history_name);
         Ok(())
@@ -76,13 +265,162 @@ impl Clipboard {
             .default(0)
             .interact()?;
         let key = options[index].clone();
-        let value = self.data[&self.current].get(&key).unwrap().to_owned();
-        let mut clipboard_ctx: ClipboardContext = ClipboardProvider::new()?;
-        clipboard_ctx.set_contents(value)?;
+        let entry = self.data[&self.current].get(&key).unwrap().to_owned();
+        let target = self.prompt_selection("Paste to:", entry.selection)?;
+        self.require_backend()?.set_contents(target, entry.text)?;
         println!("Data copied to clipboard.");
         Ok(())
     }
 
+    /// Prompt for which clipboard buffer to use, defaulting the choice to
+    /// `default`. Skips the prompt and returns the system clipboard on
+    /// backends with no real primary selection.
+    fn prompt_selection(
+        &self,
+        prompt: &str,
+        default: ClipboardType,
+    ) -> Result<ClipboardType, Box<dyn Error>> {
+        let supports_selection = self
+            .backend()
+            .as_ref()
+            .is_some_and(|backend| backend.supports_selection());
+        if !supports_selection {
+            return Ok(ClipboardType::Clipboard);
+        }
+        let options = vec!["Clipboard (system)", "Selection (primary)"];
+        let default_index = match default {
+            ClipboardType::Clipboard => 0,
+            ClipboardType::Selection => 1,
+        };
+        let index = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items(&options)
+            .default(default_index)
+            .interact()?;
+        Ok(match index {
+            0 => ClipboardType::Clipboard,
+            _ => ClipboardType::Selection,
+        })
+    }
+
+    /// Histories whose name, or one of whose keys or texts, contains `term`.
+    /// A matching history is returned whole, non-matching keys included.
+    fn matching_data(&self, term: &str) -> Data {
+        let mut matched_data = HashMap::new();
+        for (history_name, inner_map) in &self.data {
+            for (inner_key, inner_entry) in inner_map {
+                if history_name.contains(term)
+                    || inner_key.contains(term)
+                    || inner_entry.text.contains(term)
+                {
+                    matched_data.insert(history_name.clone(), inner_map.clone());
+                    break;
+                }
+            }
+        }
+        matched_data
+    }
+
+    /// Read the text to store for a non-interactive `save`: piped stdin
+    /// takes priority, falling back to the live clipboard when stdin is a
+    /// terminal (nothing was piped in).
+    fn read_input(&self) -> Result<String, Box<dyn Error>> {
+        let stdin = io::stdin();
+        if stdin.is_terminal() {
+            self.require_backend()?.get_contents(ClipboardType::Clipboard)
+        } else {
+            let mut buf = String::new();
+            stdin.lock().read_to_string(&mut buf)?;
+            Ok(buf.trim_end_matches('\n').to_string())
+        }
+    }
+
+    fn save_noninteractive(&mut self, history_name: &str, key: &str) -> Result<(), Box<dyn Error>> {
+        let entry = Entry::new(self.read_input()?, ClipboardType::Clipboard);
+        let history = self
+            .data
+            .entry(history_name.to_string())
+            .or_default();
+
+        if is_duplicate(history, &entry) {
+            eprintln!(
+                "Skipped: this text is already stored in \"{}\".",
+                history_name
+            );
+            return Ok(());
+        }
+
+        history.insert(key.to_string(), entry);
+        self.save_data()?;
+        Ok(())
+    }
+
+    fn load_noninteractive(&mut self, history_name: &str, key: &str) -> Result<(), Box<dyn Error>> {
+        let entry = self
+            .data
+            .get(history_name)
+            .and_then(|history| history.get(key))
+            .ok_or_else(|| format!("no entry \"{}\" in history \"{}\"", key, history_name))?
+            .clone();
+        println!("{}", entry.text);
+        self.require_backend()?.set_contents(entry.selection, entry.text)?;
+        Ok(())
+    }
+
+    fn list_as(&self, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+        match format {
+            OutputFormat::Json => write_json(&self.data, io::stdout()),
+            OutputFormat::Csv => write_csv(&self.data, io::stdout()),
+        }
+    }
+
+    fn search_as(&self, term: &str, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+        let matched = self.matching_data(term);
+        match format {
+            OutputFormat::Json => write_json(&matched, io::stdout()),
+            OutputFormat::Csv => write_csv(&matched, io::stdout()),
+        }
+    }
+
+    /// Report which clipboard backend was detected and whether it actually
+    /// works, so users can debug clipboard issues themselves instead of
+    /// chasing an opaque `Box<dyn Error>` out of `save`/`load`. Runs (and is
+    /// useful) even when no backend was found, which is the case this
+    /// command exists to diagnose.
+    fn report_provider(&self) -> Result<(), Box<dyn Error>> {
+        let Some(backend) = self.backend() else {
+            println!("Backend:         none detected");
+            println!(
+                "Tried:           wl-copy/wl-paste, xclip, xsel, pbcopy/pbpaste, clip.exe (platform-dependent)"
+            );
+            println!("Platform:        {}", std::env::consts::OS);
+            println!(
+                "Hint:            install one of the above, or build with --features native-clipboard"
+            );
+            return Ok(());
+        };
+        println!("Backend:         {}", backend.name());
+        match backend.executable_path() {
+            Some(path) => println!("Executable:      {}", path.display()),
+            None => println!("Executable:      (in-process, no external command)"),
+        }
+        println!("Platform:        {}", std::env::consts::OS);
+        println!(
+            "Primary selection: {}",
+            if backend.supports_selection() {
+                "supported"
+            } else {
+                "not supported (falls back to the system clipboard)"
+            }
+        );
+        println!("Self-test:       writing a sentinel value and restoring your current clipboard contents afterward...");
+        match backend::self_test(backend.as_ref()) {
+            Ok(()) => println!("Self-test:       ok (wrote and read back a sentinel value, original contents restored on a best-effort basis)"),
+            Err(err) => println!("Self-test:       FAILED ({})", err),
+        }
+        Ok(())
+    }
+
     fn list(&self) -> Result<(), Box<dyn Error>> {
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_BOX_CHARS);
@@ -91,13 +429,16 @@ impl Clipboard {
             Cell::new("History"),
             Cell::new("Key"),
             Cell::new("Value"),
+            Cell::new("Source"),
+            Cell::new(""),
         ]);
         table.add_row(header);
 
         for (history_name, map) in &self.data {
-            table.add_row(row![history_name, "", ""]);
-            for (key, value) in map {
-                table.add_row(row!["", key, value]);
+            table.add_row(row![history_name, "", "", "", ""]);
+            for (key, entry) in map {
+                let dup = if is_duplicate(map, entry) { "dup" } else { "" };
+                table.add_row(row!["", key, entry.text, entry.selection, dup]);
             }
         }
 
@@ -117,28 +458,28 @@ impl Clipboard {
             })
             .interact()?;
 
-        let mut matched_data = HashMap::new();
-        for (history_name, inner_map) in &self.data {
-            for (inner_key, inner_value) in inner_map {
-                if history_name.contains(&search_term)
-                    || inner_key.contains(&search_term)
-                    || inner_value.contains(&search_term)
-                {
-                    matched_data.insert(history_name.clone(), inner_map.clone());
-                    break;
-                }
-            }
-        }
+        let matched_data = self.matching_data(&search_term);
 
         if matched_data.is_empty() {
             println!("No results found for search term: {}", search_term);
         } else {
             let mut table = Table::new();
-            table.add_row(row!["History", "Key", "Value"]);
+            table.add_row(row!["History", "Key", "Value", "Source", ""]);
             table.set_format(*format::consts::FORMAT_BOX_CHARS);
             for (key, inner_map) in &matched_data {
-                for (inner_key, inner_value) in inner_map {
-                    table.add_row(row![key, inner_key, inner_value]);
+                for (inner_key, inner_entry) in inner_map {
+                    let dup = if is_duplicate(inner_map, inner_entry) {
+                        "dup"
+                    } else {
+                        ""
+                    };
+                    table.add_row(row![
+                        key,
+                        inner_key,
+                        inner_entry.text,
+                        inner_entry.selection,
+                        dup
+                    ]);
                 }
             }
             table.printstd();
@@ -185,7 +526,7 @@ impl Clipboard {
                     .interact()?;
                 let file = File::create(&filename)?;
                 let writer = BufWriter::new(file);
-                serde_json::to_writer_pretty(writer, &self.data)?;
+                write_json(&self.data, writer)?;
                 println!("Clipboard data exported to {}.", filename);
             }
             "CSV" => {
@@ -194,13 +535,7 @@ impl Clipboard {
                     .interact()?;
 
                 let file = File::create(&filename)?;
-                let mut writer = csv::Writer::from_writer(file);
-                for (history_name, map) in &self.data {
-                    for (key, value) in map {
-                        writer.serialize(&[history_name, key, value])?;
-                    }
-                }
-                writer.flush()?;
+                write_csv(&self.data, file)?;
                 println!("Clipboard data exported to {}.", filename);
             }
             "Exit" => {
@@ -226,8 +561,7 @@ impl Clipboard {
                     .with_prompt("Enter the filename for JSON import:")
                     .interact()?;
                 let file_content = fs::read_to_string(&filename)?;
-                let imported_data: HashMap<String, HashMap<String, String>> =
-                    serde_json::from_str(&file_content)?;
+                let imported_data: Data = serde_json::from_str(&file_content)?;
                 self.merge_data(imported_data)?;
                 println!("Data imported from {}.", filename);
             }
@@ -238,16 +572,26 @@ impl Clipboard {
 
                 let file = File::open(&filename)?;
                 let mut reader = csv::Reader::from_reader(file);
-                let mut imported_data = HashMap::new();
+                let mut imported_data: Data = HashMap::new();
                 for result in reader.deserialize() {
                     let record: Vec<String> = result?;
                     let history_name = record[0].clone();
                     let key = record[1].clone();
-                    let value = record[2].clone();
+                    let text = record[2].clone();
+                    let selection = match record.get(3).map(String::as_str) {
+                        Some("selection") => ClipboardType::Selection,
+                        _ => ClipboardType::Clipboard,
+                    };
+                    let metadata = record
+                        .get(4)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| m.to_string());
+                    let mut entry = Entry::new(text, selection);
+                    entry.metadata = metadata;
                     imported_data
                         .entry(history_name)
-                        .or_insert(HashMap::new())
-                        .insert(key, value);
+                        .or_default()
+                        .insert(key, entry);
                 }
                 self.merge_data(imported_data)?;
                 println!("Data imported from {}.", filename);
@@ -261,15 +605,12 @@ impl Clipboard {
         Ok(())
     }
 
-    fn merge_data(
-        &mut self,
-        imported_data: HashMap<String, HashMap<String, String>>,
-    ) -> Result<(), Box<dyn Error>> {
+    fn merge_data(&mut self, imported_data: Data) -> Result<(), Box<dyn Error>> {
         for (history_name, map) in imported_data {
             let existing_map = self
                 .data
                 .entry(history_name.clone())
-                .or_insert(HashMap::new());
+                .or_default();
             
             for (key, value) in map {
                 existing_map.insert(key, value);
@@ -279,19 +620,42 @@ impl Clipboard {
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn open_clipboard() -> Result<Clipboard, Box<dyn Error>> {
     let clipboard_file = Path::new("clipboard.json");
-
-    let mut clipboard = if clipboard_file.exists() {
-        let mut clipboard = Clipboard::new("clipboard.json");
+    let mut clipboard = Clipboard::new("clipboard.json");
+    if clipboard_file.exists() {
         clipboard.load_data()?;
-        clipboard
-    } else {
-        Clipboard::new("clipboard.json")
-    };
+    }
+    Ok(clipboard)
+}
+
+fn run_command(command: Command) -> Result<(), Box<dyn Error>> {
+    let mut clipboard = open_clipboard()?;
+    match command {
+        Command::Save { history, key } => clipboard.save_noninteractive(&history, &key)?,
+        Command::Load { history, key } => clipboard.load_noninteractive(&history, &key)?,
+        Command::List { format } => clipboard.list_as(format)?,
+        Command::Search { term, format } => clipboard.search_as(&term, format)?,
+        Command::Provider => clipboard.report_provider()?,
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse(&args) {
+        Ok(Some(command)) => return run_command(command),
+        Ok(None) => {}
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    }
+
+    let mut clipboard = open_clipboard()?;
 
     let choices = vec![
-        "Save", "Load", "List", "Search", "Delete", "Export", "Import", "Quit",
+        "Save", "Load", "List", "Search", "Delete", "Export", "Import", "Provider", "Quit",
     ];
     loop {
         let choice = Select::with_theme(&ColorfulTheme::default())
@@ -307,7 +671,8 @@ fn main() -> Result<(), Box<dyn Error>> {
             4 => clipboard.delete()?,
             5 => clipboard.export()?,
             6 => clipboard.import()?,
-            7 => {
+            7 => clipboard.report_provider()?,
+            8 => {
                 clipboard.save_data()?;
                 println!("Data saved before quitting.");
                 process::exit(0);
@@ -316,3 +681,44 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_entry_gets_a_hash_and_default_fields() {
+        let entry: Entry = serde_json::from_str(r#""hello world""#).unwrap();
+        assert_eq!(entry.text, "hello world");
+        assert_eq!(entry.hash, hash_text("hello world"));
+        assert_eq!(entry.selection, ClipboardType::Clipboard);
+        assert_eq!(entry.metadata, None);
+    }
+
+    #[test]
+    fn without_hash_entry_gets_a_hash_computed_from_its_value() {
+        let entry: Entry =
+            serde_json::from_str(r#"{"value": "copied text", "selection": "Selection"}"#).unwrap();
+        assert_eq!(entry.text, "copied text");
+        assert_eq!(entry.hash, hash_text("copied text"));
+        assert_eq!(entry.selection, ClipboardType::Selection);
+    }
+
+    #[test]
+    fn without_hash_entry_defaults_selection_when_absent() {
+        let entry: Entry = serde_json::from_str(r#"{"value": "copied text"}"#).unwrap();
+        assert_eq!(entry.selection, ClipboardType::Clipboard);
+    }
+
+    #[test]
+    fn current_entry_keeps_its_stored_fields_unchanged() {
+        let entry: Entry = serde_json::from_str(
+            r#"{"text": "final shape", "hash": 42, "selection": "Clipboard", "metadata": "note", "created_at": 100}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.text, "final shape");
+        assert_eq!(entry.hash, 42);
+        assert_eq!(entry.metadata, Some("note".to_string()));
+        assert_eq!(entry.created_at, 100);
+    }
+}